@@ -0,0 +1,257 @@
+// Cross-platform text injection backends.
+//
+// `insert_text` used to hardcode `xdotool`, which only works under X11. This
+// module picks a backend at runtime based on the OS and (on Linux) the
+// desktop session type, so the same command works under Wayland, Windows and
+// macOS instead of silently failing outside X11.
+
+use std::process::Command;
+
+/// A backend capable of typing text into whatever window currently has focus.
+pub trait TextInjector {
+    /// Human-readable name, used for status reporting and error messages.
+    fn name(&self) -> &'static str;
+
+    /// Types `text` into the active window.
+    fn inject(&self, text: &str) -> Result<(), String>;
+
+    /// Whether the backend's underlying tool/API is present on this system.
+    fn is_available(&self) -> bool;
+}
+
+/// X11 backend, implemented by shelling out to `xdotool`.
+pub struct XdotoolInjector;
+
+impl TextInjector for XdotoolInjector {
+    fn name(&self) -> &'static str {
+        "xdotool"
+    }
+
+    fn inject(&self, text: &str) -> Result<(), String> {
+        let output = Command::new("xdotool")
+            .arg("type")
+            .arg("--clearmodifiers")
+            .arg("--")
+            .arg(text)
+            .output()
+            .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "xdotool failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("xdotool").is_ok()
+    }
+}
+
+/// Wayland backend. Prefers `wtype` (virtual-keyboard protocol) and falls
+/// back to `ydotool` (uinput, works without compositor support but usually
+/// needs the `ydotoold` daemon running).
+pub struct WaylandInjector;
+
+impl TextInjector for WaylandInjector {
+    fn name(&self) -> &'static str {
+        "wayland"
+    }
+
+    fn inject(&self, text: &str) -> Result<(), String> {
+        if which::which("wtype").is_ok() {
+            let output = Command::new("wtype")
+                .arg(text)
+                .output()
+                .map_err(|e| format!("Failed to run wtype: {}", e))?;
+
+            if !output.status.success() {
+                return Err(format!(
+                    "wtype failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            return Ok(());
+        }
+
+        if which::which("ydotool").is_ok() {
+            let output = Command::new("ydotool")
+                .arg("type")
+                .arg("--")
+                .arg(text)
+                .output()
+                .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+
+            if !output.status.success() {
+                return Err(format!(
+                    "ydotool failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ));
+            }
+
+            return Ok(());
+        }
+
+        Err("Neither wtype nor ydotool is installed".to_string())
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("wtype").is_ok() || which::which("ydotool").is_ok()
+    }
+}
+
+/// Windows backend, typing via the `SendInput` API.
+#[cfg(target_os = "windows")]
+pub struct WindowsInjector;
+
+#[cfg(target_os = "windows")]
+impl TextInjector for WindowsInjector {
+    fn name(&self) -> &'static str {
+        "sendinput"
+    }
+
+    fn inject(&self, text: &str) -> Result<(), String> {
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP,
+            KEYEVENTF_UNICODE,
+        };
+
+        let inputs: Vec<INPUT> = text
+            .encode_utf16()
+            .flat_map(|code_unit| {
+                let make = |flags: u32| INPUT {
+                    r#type: INPUT_KEYBOARD,
+                    Anonymous: INPUT_0 {
+                        ki: KEYBDINPUT {
+                            wVk: 0,
+                            wScan: code_unit,
+                            dwFlags: KEYEVENTF_UNICODE | flags,
+                            time: 0,
+                            dwExtraInfo: 0,
+                        },
+                    },
+                };
+                [make(0), make(KEYEVENTF_KEYUP)]
+            })
+            .collect();
+
+        let sent = unsafe {
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_ptr(),
+                std::mem::size_of::<INPUT>() as i32,
+            )
+        };
+
+        if sent as usize != inputs.len() {
+            return Err("SendInput did not inject all keystrokes".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// macOS backend. Uses `osascript` to drive System Events, which is
+/// simpler to ship than linking CGEvent directly and needs no extra
+/// entitlements beyond the Accessibility permission prompt macOS already
+/// shows for either approach.
+#[cfg(target_os = "macos")]
+pub struct MacInjector;
+
+#[cfg(target_os = "macos")]
+impl TextInjector for MacInjector {
+    fn name(&self) -> &'static str {
+        "osascript"
+    }
+
+    fn inject(&self, text: &str) -> Result<(), String> {
+        let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "tell application \"System Events\" to keystroke \"{}\"",
+            escaped
+        );
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .map_err(|e| format!("Failed to run osascript: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "osascript failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("osascript").is_ok()
+    }
+}
+
+/// Whether `XDG_SESSION_TYPE` reports a Wayland desktop session.
+fn linux_session_is_wayland() -> bool {
+    std::env::var("XDG_SESSION_TYPE")
+        .map(|session| session.eq_ignore_ascii_case("wayland"))
+        .unwrap_or(false)
+}
+
+/// Builds the injector named by a settings preference, if it's valid for
+/// this OS. Returns `None` for an unknown or platform-mismatched name so
+/// the caller can fall back to auto-detection.
+fn injector_by_name(name: &str) -> Option<Box<dyn TextInjector>> {
+    match name {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        "xdotool" => Some(Box::new(XdotoolInjector)),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        "wayland" => Some(Box::new(WaylandInjector)),
+        #[cfg(target_os = "windows")]
+        "sendinput" => Some(Box::new(WindowsInjector)),
+        #[cfg(target_os = "macos")]
+        "osascript" => Some(Box::new(MacInjector)),
+        _ => None,
+    }
+}
+
+/// Picks the injector appropriate for the current OS and desktop session,
+/// honoring `preference` (a backend name from `Settings::injection_backend`)
+/// when it names a valid backend for this platform.
+pub fn detect_injector(preference: Option<&str>) -> Box<dyn TextInjector> {
+    if let Some(name) = preference {
+        if let Some(injector) = injector_by_name(name) {
+            return injector;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        return Box::new(WindowsInjector);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        return Box::new(MacInjector);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if linux_session_is_wayland() {
+            return Box::new(WaylandInjector);
+        }
+        return Box::new(XdotoolInjector);
+    }
+
+    #[allow(unreachable_code)]
+    Box::new(XdotoolInjector)
+}
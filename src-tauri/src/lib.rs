@@ -1,20 +1,59 @@
+mod injector;
+mod plugins;
+mod settings;
+mod supervisor;
+mod tts;
+
+use injector::detect_injector;
+use plugins::{PluginInfo, PluginRegistry};
 use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsState};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
 use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
-use tauri::State;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use supervisor::SupervisionError;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tts::{TtsState, Voice};
+
+/// Bound on how long `stop_recording` waits for the nerd-dictation child to
+/// exit before escalating to a kill. Independent of the recording's own
+/// `--timeout` (which can be minutes long): this is just enough slack for
+/// the process to notice `end` and flush before we consider it hung.
+const CHILD_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Default)]
 struct VoiceState {
     process: Mutex<Option<Child>>,
     model_path: Mutex<String>,
+    reader_handle: Mutex<Option<JoinHandle<()>>>,
+    stderr_handle: Mutex<Option<JoinHandle<()>>>,
+    stop_reader: Arc<AtomicBool>,
+    transcript: Arc<Mutex<String>>,
+    stderr_buffer: Arc<Mutex<String>>,
+    plugins: PluginRegistry,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VoskResult {
+    #[serde(default)]
+    partial: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct RecordingConfig {
-    timeout: u32,
     model_size: String,
-    auto_punctuation: bool,
-    numbers_as_digits: bool,
+    #[serde(default)]
+    timeout: Option<u32>,
+    #[serde(default)]
+    auto_punctuation: Option<bool>,
+    #[serde(default)]
+    numbers_as_digits: Option<bool>,
 }
 
 // Start voice recording using nerd-dictation
@@ -22,28 +61,31 @@ struct RecordingConfig {
 async fn start_recording(
     config: RecordingConfig,
     state: State<'_, VoiceState>,
+    settings_state: State<'_, SettingsState>,
+    app_handle: AppHandle,
 ) -> Result<(), String> {
-    // Determine model path based on size
-    let home = std::env::var("HOME").unwrap_or_default();
-    let model_name = if config.model_size == "large" {
-        "vosk-model-en-us-0.22"
-    } else {
-        "vosk-model-small-en-us-0.15"
-    };
-    let model_dir = format!("{}/.local/share/vosk-models/{}", home, model_name);
+    let settings = settings_state.settings.lock().unwrap().clone();
+
+    // Resolve the model key (not just the hardcoded "small"/"large") from
+    // the user-editable model registry instead of recomputing a $HOME path
+    let model_dir = settings
+        .model_path(&config.model_size)
+        .ok_or_else(|| {
+            format!(
+                "Unknown model '{}'. Add it via update_settings first.",
+                config.model_size
+            )
+        })?
+        .to_string();
 
     // Save model path for later use
     *state.model_path.lock().unwrap() = model_dir.clone();
 
     // Check if nerd-dictation is available
-    let nerd_dictation_path = which::which("nerd-dictation")
-        .or_else(|_| {
-            let home = std::env::var("HOME").unwrap_or_default();
-            which::which(format!("{}/.local/bin/nerd-dictation", home))
-        })
-        .map_err(|e| format!("nerd-dictation not found: {}", e))?;
+    let nerd_dictation_path = settings.resolve_nerd_dictation_path()?;
 
     // Get config file path
+    let home = std::env::var("HOME").unwrap_or_default();
     let config_file = format!("{}/.config/nerd-dictation/nerd-dictation.py", home);
 
     // Validate config file exists
@@ -51,139 +93,218 @@ async fn start_recording(
         return Err(format!("Configuration file not found: {}. Please create the nerd-dictation config file.", config_file));
     }
 
+    let timeout = config.timeout.unwrap_or(settings.timeout);
+    let auto_punctuation = config.auto_punctuation.unwrap_or(settings.auto_punctuation);
+    let numbers_as_digits = config
+        .numbers_as_digits
+        .unwrap_or(settings.numbers_as_digits);
+
     // Build command with configuration that outputs to STDOUT instead of typing
     let mut cmd = Command::new(nerd_dictation_path);
     cmd.arg("begin")
         .arg("--vosk-model-dir")
         .arg(&model_dir)
         .arg("--timeout")
-        .arg(config.timeout.to_string())
+        .arg(timeout.to_string())
         .arg("--config")
         .arg(&config_file)
         .arg("--output")
         .arg("STDOUT")
-        .arg("--defer-output")  // This ensures output is deferred until we call 'end'
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
     // Add numbers as digits flag if enabled
-    if config.numbers_as_digits {
+    if numbers_as_digits {
         cmd.arg("--numbers-as-digits");
     }
 
     // Add auto punctuation if enabled
-    if config.auto_punctuation {
+    if auto_punctuation {
         cmd.arg("--full-sentence");
     }
 
+    // If a previous recording was never stopped, kill and reap it now so we
+    // don't leak a process (and its reader threads) on every restart. Reap
+    // the child *before* joining its reader threads: they block on a
+    // blocking pipe read, which only unblocks once the child's end closes,
+    // so joining first would hang just as long as the stale child does.
+    if let Some(stale_child) = state.process.lock().unwrap().take() {
+        state.stop_reader.store(true, Ordering::SeqCst);
+        supervisor::kill_and_reap(stale_child);
+        if let Some(handle) = state.reader_handle.lock().unwrap().take() {
+            supervisor::join_reader(handle);
+        }
+        if let Some(handle) = state.stderr_handle.lock().unwrap().take() {
+            supervisor::join_reader(handle);
+        }
+    }
+
     // Start the process
-    let child = cmd
+    let mut child = cmd
         .spawn()
         .map_err(|e| format!("Failed to start recording: {}", e))?;
 
-    // Store the process handle
+    // Reset state left over from a previous session
+    *state.transcript.lock().unwrap() = String::new();
+    *state.stderr_buffer.lock().unwrap() = String::new();
+    state.stop_reader.store(false, Ordering::SeqCst);
+
+    // Drain stderr on its own thread so a full pipe buffer can't block the
+    // child if nothing reads it while recording is in progress
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture recording stderr".to_string())?;
+    let stderr_buffer = state.stderr_buffer.clone();
+    let stderr_handle = std::thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            let mut buffer = stderr_buffer.lock().unwrap();
+            if !buffer.is_empty() {
+                buffer.push('\n');
+            }
+            buffer.push_str(&line);
+        }
+    });
+
+    // Stream Vosk's line-buffered JSON results to the frontend as they arrive
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Failed to capture recording stdout".to_string())?;
+    let stop_reader = state.stop_reader.clone();
+    let transcript = state.transcript.clone();
+    let reader_handle = std::thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines() {
+            if stop_reader.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(result) = serde_json::from_str::<VoskResult>(&line) else {
+                continue;
+            };
+            if let Some(text) = result.text {
+                if !text.is_empty() {
+                    let mut transcript = transcript.lock().unwrap();
+                    if !transcript.is_empty() {
+                        transcript.push(' ');
+                    }
+                    transcript.push_str(&text);
+                }
+                let _ = app_handle.emit("transcript-final", text);
+            } else if let Some(partial) = result.partial {
+                let _ = app_handle.emit("transcript-partial", partial);
+            }
+        }
+    });
+
+    // Store the process and reader thread handles
     *state.process.lock().unwrap() = Some(child);
+    *state.reader_handle.lock().unwrap() = Some(reader_handle);
+    *state.stderr_handle.lock().unwrap() = Some(stderr_handle);
 
     Ok(())
 }
 
 // Stop voice recording
 #[tauri::command]
-async fn stop_recording(state: State<'_, VoiceState>) -> Result<String, String> {
+async fn stop_recording(
+    state: State<'_, VoiceState>,
+    settings_state: State<'_, SettingsState>,
+) -> Result<String, SupervisionError> {
     // Get the stored process
     let child_opt = state.process.lock().unwrap().take();
-    
+
     if let Some(child) = child_opt {
         // End nerd-dictation first
-        let nerd_dictation_path = which::which("nerd-dictation")
-            .or_else(|_| {
-                let home = std::env::var("HOME").unwrap_or_default();
-                which::which(format!("{}/.local/bin/nerd-dictation", home))
-            })
-            .map_err(|e| format!("nerd-dictation not found: {}", e))?;
+        let nerd_dictation_path = settings_state
+            .settings
+            .lock()
+            .unwrap()
+            .resolve_nerd_dictation_path()
+            .map_err(SupervisionError::Other)?;
 
         let _end_output = Command::new(nerd_dictation_path)
             .arg("end")
             .output()
-            .map_err(|e| format!("Failed to end recording: {}", e))?;
-
-        // Now wait for the process to complete and capture its stdout
-        let output = child
-            .wait_with_output()
-            .map_err(|e| format!("Failed to read recording output: {}", e))?;
-
-        // Get the transcribed text from stdout
-        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        
-        // If there's an error in stderr, log it but don't fail
-        if !output.stderr.is_empty() {
-            let stderr_text = String::from_utf8_lossy(&output.stderr);
+            .map_err(|e| SupervisionError::Other(format!("Failed to end recording: {}", e)))?;
+
+        state.stop_reader.store(true, Ordering::SeqCst);
+
+        // Reap the process within a bounded wait (escalating to SIGTERM/
+        // SIGKILL if it hangs) *before* joining the reader threads. They
+        // block on a blocking pipe read, which only unblocks once the
+        // child's end closes — joining first would block just as long as
+        // a hung nerd-dictation process does, defeating the bounded wait.
+        let wait_result = supervisor::wait_with_timeout(child, CHILD_WAIT_TIMEOUT);
+
+        // Now that the process is gone and its pipes are closed, the
+        // reader threads will drain what's left and return quickly
+        if let Some(handle) = state.reader_handle.lock().unwrap().take() {
+            supervisor::join_reader(handle);
+        }
+        if let Some(handle) = state.stderr_handle.lock().unwrap().take() {
+            supervisor::join_reader(handle);
+        }
+
+        let stderr_text = state.stderr_buffer.lock().unwrap().clone();
+        if !stderr_text.is_empty() {
             eprintln!("nerd-dictation stderr: {}", stderr_text);
         }
 
-        Ok(text)
+        wait_result?;
+
+        let text = state.transcript.lock().unwrap().clone();
+
+        // Run the transcript through any enabled post-processing plugins
+        // (punctuation restoration, profanity filtering, LLM cleanup, ...)
+        state
+            .plugins
+            .run_chain(text)
+            .map_err(SupervisionError::Other)
     } else {
         // No process was running
-        Err("No recording process found".to_string())
+        Err(SupervisionError::Other(
+            "No recording process found".to_string(),
+        ))
     }
 }
 
-// Insert text into active window using xdotool
+// Insert text into the active window using whichever backend suits the
+// current OS/session (X11, Wayland, Windows or macOS)
 #[tauri::command]
-async fn insert_text(text: String) -> Result<(), String> {
-    // Use xdotool to type the text
-    let output = Command::new("xdotool")
-        .arg("type")
-        .arg("--clearmodifiers")
-        .arg("--")
-        .arg(&text)
-        .output()
-        .map_err(|e| format!("Failed to insert text: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!(
-            "xdotool failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    Ok(())
+async fn insert_text(text: String, settings_state: State<'_, SettingsState>) -> Result<(), String> {
+    let preference = settings_state.settings.lock().unwrap().injection_backend.clone();
+    detect_injector(preference.as_deref()).inject(&text)
 }
 
 // Check if voice system is available
 #[tauri::command]
-async fn check_voice_system() -> Result<VoiceSystemStatus, String> {
+async fn check_voice_system(
+    settings_state: State<'_, SettingsState>,
+) -> Result<VoiceSystemStatus, String> {
+    let settings = settings_state.settings.lock().unwrap().clone();
+    let injector = detect_injector(settings.injection_backend.as_deref());
+
     let mut status = VoiceSystemStatus {
-        nerd_dictation: false,
-        xdotool: false,
-        vosk_model_small: false,
-        vosk_model_large: false,
+        nerd_dictation: settings.resolve_nerd_dictation_path().is_ok(),
+        injection_backend: injector.name().to_string(),
+        injection_backend_available: injector.is_available(),
+        models: HashMap::new(),
         microphone: false,
     };
 
-    // Check nerd-dictation
-    status.nerd_dictation = which::which("nerd-dictation").is_ok()
-        || which::which(format!("{}/.local/bin/nerd-dictation",
-            std::env::var("HOME").unwrap_or_default())).is_ok();
-
-    // Check xdotool
-    status.xdotool = which::which("xdotool").is_ok();
-
-    // Check Vosk models
-    let home = std::env::var("HOME").unwrap_or_default();
-    let models_dir = format!("{}/.local/share/vosk-models", home);
-
-    status.vosk_model_small = std::path::Path::new(&format!(
-        "{}/vosk-model-small-en-us-0.15",
-        models_dir
-    ))
-    .exists();
-
-    status.vosk_model_large = std::path::Path::new(&format!(
-        "{}/vosk-model-en-us-0.22",
-        models_dir
-    ))
-    .exists();
+    // Check each registered model (not just the hardcoded small/large pair)
+    for (key, path) in &settings.models {
+        status
+            .models
+            .insert(key.clone(), std::path::Path::new(path).exists());
+    }
 
     // Check microphone (using pactl)
     if let Ok(output) = Command::new("pactl")
@@ -199,23 +320,125 @@ async fn check_voice_system() -> Result<VoiceSystemStatus, String> {
 #[derive(Debug, Serialize)]
 struct VoiceSystemStatus {
     nerd_dictation: bool,
-    xdotool: bool,
-    vosk_model_small: bool,
-    vosk_model_large: bool,
+    injection_backend: String,
+    injection_backend_available: bool,
+    models: HashMap<String, bool>,
     microphone: bool,
 }
 
+// Read text aloud through the platform's speech-synthesis backend
+#[tauri::command]
+async fn speak_text(text: String, state: State<'_, TtsState>) -> Result<(), String> {
+    let params = state.params.lock().unwrap().clone();
+    state.backend.speak(&text, &params)
+}
+
+// Stop any speech currently in progress
+#[tauri::command]
+async fn stop_speaking(state: State<'_, TtsState>) -> Result<(), String> {
+    state.backend.stop()
+}
+
+// List the voices available from the active speech-synthesis backend
+#[tauri::command]
+async fn list_voices(state: State<'_, TtsState>) -> Result<Vec<Voice>, String> {
+    state.backend.list_voices()
+}
+
+// Select which voice subsequent speak_text calls should use
+#[tauri::command]
+async fn set_tts_voice(voice_id: String, state: State<'_, TtsState>) -> Result<(), String> {
+    state.params.lock().unwrap().voice_id = Some(voice_id);
+    Ok(())
+}
+
+// Adjust speaking rate, pitch and volume (each a 0.0-2.0 multiplier, 1.0 = default)
+#[tauri::command]
+async fn set_tts_params(
+    rate: f32,
+    pitch: f32,
+    volume: f32,
+    state: State<'_, TtsState>,
+) -> Result<(), String> {
+    let mut params = state.params.lock().unwrap();
+    params.rate = rate;
+    params.pitch = pitch;
+    params.volume = volume;
+    Ok(())
+}
+
+// Register an external post-processing plugin, spawning it so it can
+// describe itself before it's added to the transform chain
+#[tauri::command]
+async fn register_plugin(
+    path: String,
+    config: serde_json::Value,
+    state: State<'_, VoiceState>,
+) -> Result<PluginInfo, String> {
+    state.plugins.register(path, config)
+}
+
+// List the registered post-processing plugins and what they advertised
+#[tauri::command]
+async fn list_plugins(state: State<'_, VoiceState>) -> Result<Vec<PluginInfo>, String> {
+    Ok(state.plugins.list())
+}
+
+// Enable or disable a registered plugin without unregistering it
+#[tauri::command]
+async fn set_plugin_enabled(
+    path: String,
+    enabled: bool,
+    state: State<'_, VoiceState>,
+) -> Result<(), String> {
+    state.plugins.set_enabled(&path, enabled)
+}
+
+// Read the current persisted settings
+#[tauri::command]
+async fn get_settings(settings_state: State<'_, SettingsState>) -> Result<Settings, String> {
+    Ok(settings_state.settings.lock().unwrap().clone())
+}
+
+// Replace the persisted settings and write them to disk
+#[tauri::command]
+async fn update_settings(
+    settings: Settings,
+    settings_state: State<'_, SettingsState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    settings.save(&app_handle)?;
+    *settings_state.settings.lock().unwrap() = settings;
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .manage(VoiceState::default())
+        .manage(TtsState::default())
+        .setup(|app| {
+            let settings = Settings::load(app.handle());
+            app.manage(SettingsState::new(settings));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             start_recording,
             stop_recording,
             insert_text,
-            check_voice_system
+            check_voice_system,
+            speak_text,
+            stop_speaking,
+            list_voices,
+            set_tts_voice,
+            set_tts_params,
+            register_plugin,
+            list_plugins,
+            set_plugin_enabled,
+            get_settings,
+            update_settings
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
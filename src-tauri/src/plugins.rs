@@ -0,0 +1,400 @@
+// Post-processing plugin pipeline.
+//
+// Plugins are external programs the user registers to transform a
+// transcript before it gets inserted (punctuation restoration, profanity
+// filtering, command interpretation, LLM cleanup, ...). Each plugin is
+// spawned as a child process and speaks a small newline-delimited
+// JSON-RPC protocol over its stdin/stdout:
+//
+//   host -> plugin: {"method":"describe"}
+//   plugin -> host: {"result":{"name":"...","wants_partial":false}}
+//
+//   host -> plugin: {"method":"transform","params":{"text":"...","config":{...}}}
+//   plugin -> host: {"result":{"text":"..."}}  or  {"error":"..."}
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{mpsc, Mutex};
+
+/// Per-plugin configuration, held in memory as part of the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub path: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub config: Value,
+}
+
+/// What a registered plugin reports about itself, for display in the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub path: String,
+    pub enabled: bool,
+    pub name: Option<String>,
+    pub wants_partial: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DescribeResult {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    wants_partial: bool,
+}
+
+#[derive(Debug, Deserialize)]
+// The derived bound would otherwise require `T: Default` just because
+// `result` is `#[serde(default)]`, even though `Option<T>: Default` holds
+// unconditionally; override it to only require what deserializing `T`
+// actually needs.
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct RpcResponse<T> {
+    #[serde(default)]
+    result: Option<T>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransformResult {
+    text: String,
+}
+
+/// How long to wait for a plugin's reply before treating it as hung.
+const PLUGIN_RPC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// A running plugin process plus what it told us about itself on startup.
+struct PluginProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// `None` while a call's reader thread is outstanding, or permanently
+    /// after a call times out and the plugin is killed.
+    stdout: Option<BufReader<std::process::ChildStdout>>,
+    name: Option<String>,
+    wants_partial: bool,
+}
+
+impl PluginProcess {
+    fn spawn(path: &str) -> Result<Self, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start plugin {}: {}", path, e))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| format!("Failed to open stdin for plugin {}", path))?;
+        let stdout = BufReader::new(
+            child
+                .stdout
+                .take()
+                .ok_or_else(|| format!("Failed to open stdout for plugin {}", path))?,
+        );
+
+        let mut process = PluginProcess {
+            child,
+            stdin,
+            stdout: Some(stdout),
+            name: None,
+            wants_partial: false,
+        };
+
+        let describe = process.call::<DescribeResult>("describe", Value::Null)?;
+        process.name = describe.name;
+        process.wants_partial = describe.wants_partial;
+
+        Ok(process)
+    }
+
+    /// Sends one JSON-RPC request and waits for its reply, bounded by
+    /// `PLUGIN_RPC_TIMEOUT`. A plugin that hangs mid-reply is killed and
+    /// reaped so it can't block the caller (e.g. `stop_recording`) forever;
+    /// the process is left unusable afterward so the registry respawns it
+    /// on the next call.
+    fn call<T: for<'de> Deserialize<'de>>(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, String> {
+        let request = serde_json::json!({ "method": method, "params": params });
+        let mut line = serde_json::to_string(&request)
+            .map_err(|e| format!("Failed to encode plugin request: {}", e))?;
+        line.push('\n');
+
+        self.stdin
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write to plugin: {}", e))?;
+        self.stdin
+            .flush()
+            .map_err(|e| format!("Failed to flush plugin stdin: {}", e))?;
+
+        let mut stdout = self
+            .stdout
+            .take()
+            .ok_or_else(|| "Plugin connection is no longer usable".to_string())?;
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut reply = String::new();
+            let result = stdout.read_line(&mut reply).map(|_| reply);
+            let _ = tx.send((stdout, result));
+        });
+
+        let reply = match rx.recv_timeout(PLUGIN_RPC_TIMEOUT) {
+            Ok((stdout, Ok(reply))) => {
+                self.stdout = Some(stdout);
+                reply
+            }
+            Ok((stdout, Err(e))) => {
+                self.stdout = Some(stdout);
+                return Err(format!("Failed to read plugin reply: {}", e));
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let _ = self.child.kill();
+                let _ = self.child.wait();
+                return Err(format!(
+                    "Plugin timed out after {:?} waiting for a reply",
+                    PLUGIN_RPC_TIMEOUT
+                ));
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err("Plugin reader thread disconnected unexpectedly".to_string());
+            }
+        };
+
+        if reply.trim().is_empty() {
+            return Err("Plugin closed its connection unexpectedly".to_string());
+        }
+
+        let response: RpcResponse<T> = serde_json::from_str(&reply)
+            .map_err(|e| format!("Failed to parse plugin reply: {}", e))?;
+
+        if let Some(error) = response.error {
+            return Err(error);
+        }
+
+        response
+            .result
+            .ok_or_else(|| "Plugin reply had neither result nor error".to_string())
+    }
+
+    fn transform(&mut self, text: &str, config: &Value) -> Result<String, String> {
+        let params = serde_json::json!({ "text": text, "config": config });
+        Ok(self.call::<TransformResult>("transform", params)?.text)
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct PluginEntry {
+    config: PluginConfig,
+    process: Option<PluginProcess>,
+}
+
+/// Registry of configured post-processing plugins, stored in `VoiceState`.
+#[derive(Default)]
+pub struct PluginRegistry {
+    entries: Mutex<Vec<PluginEntry>>,
+}
+
+impl PluginRegistry {
+    /// Registers a plugin, spawning it immediately so it can describe itself.
+    pub fn register(&self, path: String, config: Value) -> Result<PluginInfo, String> {
+        let process = PluginProcess::spawn(&path)?;
+        let info = PluginInfo {
+            path: path.clone(),
+            enabled: true,
+            name: process.name.clone(),
+            wants_partial: process.wants_partial,
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.config.path != path);
+        entries.push(PluginEntry {
+            config: PluginConfig {
+                path,
+                enabled: true,
+                config,
+            },
+            process: Some(process),
+        });
+
+        Ok(info)
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|entry| PluginInfo {
+                path: entry.config.path.clone(),
+                enabled: entry.config.enabled,
+                name: entry.process.as_ref().and_then(|p| p.name.clone()),
+                wants_partial: entry
+                    .process
+                    .as_ref()
+                    .map(|p| p.wants_partial)
+                    .unwrap_or(false),
+            })
+            .collect()
+    }
+
+    pub fn set_enabled(&self, path: &str, enabled: bool) -> Result<(), String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .iter_mut()
+            .find(|entry| entry.config.path == path)
+            .ok_or_else(|| format!("No plugin registered at {}", path))?;
+        entry.config.enabled = enabled;
+        Ok(())
+    }
+
+    /// Runs `text` through every enabled plugin in registration order,
+    /// feeding each plugin's output into the next.
+    pub fn run_chain(&self, text: String) -> Result<String, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut text = text;
+
+        for entry in entries.iter_mut() {
+            if !entry.config.enabled {
+                continue;
+            }
+
+            if entry.process.is_none() {
+                entry.process = Some(PluginProcess::spawn(&entry.config.path)?);
+            }
+
+            let process = entry.process.as_mut().unwrap();
+            let result = process.transform(&text, &entry.config.config);
+
+            // A timed-out plugin was killed and its connection is no longer
+            // usable; drop it so the next call respawns a fresh process
+            // instead of repeatedly hitting the same dead one.
+            match result {
+                Ok(transformed) => text = transformed,
+                Err(e) => {
+                    entry.process = None;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Spawns `script` under `sh -c` and wires it up as a `PluginProcess`,
+    /// bypassing the normal `describe` handshake so each test can drive the
+    /// protocol directly.
+    fn spawn_script(script: &str) -> PluginProcess {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        PluginProcess {
+            child,
+            stdin,
+            stdout: Some(stdout),
+            name: None,
+            wants_partial: false,
+        }
+    }
+
+    /// Writes `script` to a fresh, executable temp file and returns its path,
+    /// for tests that need a real `path` to hand to `PluginProcess::spawn`.
+    fn write_plugin_script(script: &str) -> std::path::PathBuf {
+        use std::io::Write as _;
+        use std::os::unix::fs::PermissionsExt;
+
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "voice-dictation-test-plugin-{}-{}.sh",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(script.as_bytes()).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn call_returns_the_plugins_result() {
+        let mut process = spawn_script("read line; echo '{\"result\":{\"text\":\"ok\"}}'");
+        let result: TransformResult = process.call("transform", Value::Null).unwrap();
+        assert_eq!(result.text, "ok");
+    }
+
+    #[test]
+    fn call_surfaces_a_plugin_reported_error() {
+        let mut process = spawn_script("read line; echo '{\"error\":\"boom\"}'");
+        let result = process.call::<TransformResult>("transform", Value::Null);
+        assert_eq!(result.unwrap_err(), "boom");
+    }
+
+    #[test]
+    fn call_times_out_and_kills_a_hung_plugin() {
+        let mut process = spawn_script("sleep 100");
+        let result = process.call::<TransformResult>("transform", Value::Null);
+        let message = result.unwrap_err();
+        assert!(message.contains("timed out"), "unexpected message: {}", message);
+        // The timeout handler must have killed and reaped the child rather
+        // than leaving it running in the background.
+        assert!(process.child.try_wait().unwrap().is_some());
+    }
+
+    #[test]
+    fn run_chain_drops_and_respawns_a_plugin_after_a_transform_error() {
+        // Always describes itself successfully, but fails every transform
+        // call so each `run_chain` invocation exercises the drop-on-error
+        // and respawn-on-next-call paths.
+        let script = write_plugin_script(
+            "#!/bin/sh\n\
+             while IFS= read -r line; do\n\
+               case \"$line\" in\n\
+                 *'\"method\":\"describe\"'*) echo '{\"result\":{\"name\":\"fake\",\"wants_partial\":false}}' ;;\n\
+                 *) echo '{\"error\":\"boom\"}' ;;\n\
+               esac\n\
+             done\n",
+        );
+
+        let registry = PluginRegistry::default();
+        registry
+            .register(script.to_string_lossy().to_string(), Value::Null)
+            .unwrap();
+
+        assert_eq!(registry.run_chain("hello".to_string()).unwrap_err(), "boom");
+        assert!(registry.entries.lock().unwrap()[0].process.is_none());
+
+        // The next call must spawn a fresh process rather than reuse the
+        // dead one; it fails the same way, but getting that far at all
+        // proves a respawn happened.
+        assert_eq!(registry.run_chain("hello".to_string()).unwrap_err(), "boom");
+        assert!(registry.entries.lock().unwrap()[0].process.is_none());
+
+        let _ = std::fs::remove_file(&script);
+    }
+}
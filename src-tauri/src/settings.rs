@@ -0,0 +1,125 @@
+// Persistent app settings.
+//
+// Model paths, the nerd-dictation binary location and recording defaults
+// used to be reconstructed from `$HOME` and string literals on every call.
+// `Settings` centralizes them in one serde struct persisted as TOML under
+// the Tauri app config dir, so they're resolved once and can be edited by
+// the user instead of hardcoded.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    /// Directory the default `models` entries are built under. Entries in
+    /// `models` are used as given; a bare name is not resolved against it.
+    pub model_dir: String,
+    /// Named Vosk model registry (key -> absolute model directory), e.g.
+    /// "small"/"large" by default, but any user-defined key works.
+    pub models: HashMap<String, String>,
+    /// Explicit path to the nerd-dictation binary; falls back to PATH/
+    /// `~/.local/bin` lookup when unset.
+    pub nerd_dictation_path: Option<String>,
+    /// Forces a specific text-injection backend name (see `injector`)
+    /// instead of auto-detecting one from the OS/session type.
+    pub injection_backend: Option<String>,
+    pub timeout: u32,
+    pub auto_punctuation: bool,
+    pub numbers_as_digits: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let home = std::env::var("HOME").unwrap_or_default();
+        let model_dir = format!("{}/.local/share/vosk-models", home);
+
+        let mut models = HashMap::new();
+        models.insert(
+            "small".to_string(),
+            format!("{}/vosk-model-small-en-us-0.15", model_dir),
+        );
+        models.insert(
+            "large".to_string(),
+            format!("{}/vosk-model-en-us-0.22", model_dir),
+        );
+
+        Self {
+            model_dir,
+            models,
+            nerd_dictation_path: None,
+            injection_backend: None,
+            timeout: 30,
+            auto_punctuation: false,
+            numbers_as_digits: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Resolves a registered model key ("small", "large", or any
+    /// user-added entry) to its directory.
+    pub fn model_path(&self, key: &str) -> Option<&str> {
+        self.models.get(key).map(|s| s.as_str())
+    }
+
+    /// Resolves the nerd-dictation binary, preferring an explicitly
+    /// configured path over PATH/`~/.local/bin` detection.
+    pub fn resolve_nerd_dictation_path(&self) -> Result<PathBuf, String> {
+        if let Some(path) = &self.nerd_dictation_path {
+            return which::which(path)
+                .map_err(|e| format!("nerd-dictation not found at configured path {}: {}", path, e));
+        }
+
+        which::which("nerd-dictation")
+            .or_else(|_| {
+                let home = std::env::var("HOME").unwrap_or_default();
+                which::which(format!("{}/.local/bin/nerd-dictation", home))
+            })
+            .map_err(|e| format!("nerd-dictation not found: {}", e))
+    }
+
+    fn file_path(app: &AppHandle) -> Result<PathBuf, String> {
+        let dir = app
+            .path()
+            .app_config_dir()
+            .map_err(|e| format!("Failed to resolve app config dir: {}", e))?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create app config dir: {}", e))?;
+        Ok(dir.join(SETTINGS_FILE))
+    }
+
+    /// Loads settings from disk, falling back to defaults if the file is
+    /// missing or unreadable.
+    pub fn load(app: &AppHandle) -> Self {
+        Self::file_path(app)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::file_path(app)?;
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        std::fs::write(path, contents).map_err(|e| format!("Failed to write settings: {}", e))
+    }
+}
+
+/// Tauri-managed state wrapping the current settings.
+pub struct SettingsState {
+    pub settings: Mutex<Settings>,
+}
+
+impl SettingsState {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+        }
+    }
+}
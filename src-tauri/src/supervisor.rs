@@ -0,0 +1,167 @@
+// Child-process supervision: bounded waits and zombie prevention.
+//
+// `wait_with_timeout` replaces a bare `child.wait()`/`wait_with_output()`
+// call, which blocks forever if the child hangs. It waits for the child on
+// a background thread and uses `recv_timeout` to bound how long the caller
+// waits; on timeout it escalates from SIGTERM to SIGKILL and always reaps
+// the process so it never lingers as a zombie.
+
+use serde::Serialize;
+use std::process::{Child, Command};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long to give a process to exit after SIGTERM before escalating to
+/// SIGKILL.
+const GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How long to give a reader thread to notice its pipe closed and return,
+/// once the process on the other end has already been reaped.
+const READER_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum SupervisionError {
+    /// The process didn't exit within the bound and was asked to terminate.
+    TimedOut,
+    /// The process didn't honor SIGTERM and had to be force-killed.
+    Killed,
+    /// The process exited on its own with a non-zero status.
+    ExitedNonZero(i32),
+    /// Waiting on the process itself failed.
+    Other(String),
+}
+
+impl std::fmt::Display for SupervisionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SupervisionError::TimedOut => write!(f, "process timed out and was terminated"),
+            SupervisionError::Killed => write!(f, "process did not respond to termination and was killed"),
+            SupervisionError::ExitedNonZero(code) => write!(f, "process exited with status {}", code),
+            SupervisionError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Waits for `child` to exit, bounded by `timeout`. Escalates to SIGTERM
+/// then SIGKILL if it doesn't, and always reaps the process.
+pub fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<(), SupervisionError> {
+    let pid = child.id();
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let status = child.wait();
+        let _ = tx.send(status);
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(status)) => {
+            if status.success() {
+                Ok(())
+            } else {
+                Err(SupervisionError::ExitedNonZero(status.code().unwrap_or(-1)))
+            }
+        }
+        Ok(Err(e)) => Err(SupervisionError::Other(format!(
+            "Failed to wait for process: {}",
+            e
+        ))),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(SupervisionError::Other(
+            "Process wait thread disconnected unexpectedly".to_string(),
+        )),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            send_signal(pid, "-TERM");
+            if rx.recv_timeout(GRACE_PERIOD).is_ok() {
+                Err(SupervisionError::TimedOut)
+            } else {
+                send_signal(pid, "-KILL");
+                // The wait thread reaps the process once it actually exits;
+                // give it a little longer so we don't leave immediately.
+                let _ = rx.recv_timeout(GRACE_PERIOD);
+                Err(SupervisionError::Killed)
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: &str) {
+    let _ = Command::new("kill").arg(signal).arg(pid.to_string()).output();
+}
+
+#[cfg(windows)]
+fn send_signal(pid: u32, _signal: &str) {
+    let _ = Command::new("taskkill")
+        .args(["/F", "/PID", &pid.to_string()])
+        .output();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_with_timeout_succeeds_for_a_fast_exiting_child() {
+        let child = Command::new("sh").arg("-c").arg("exit 0").spawn().unwrap();
+        assert!(wait_with_timeout(child, Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn wait_with_timeout_reports_a_non_zero_exit() {
+        let child = Command::new("sh").arg("-c").arg("exit 7").spawn().unwrap();
+        let result = wait_with_timeout(child, Duration::from_secs(5));
+        assert!(matches!(result, Err(SupervisionError::ExitedNonZero(7))));
+    }
+
+    #[test]
+    fn wait_with_timeout_returns_timed_out_when_sigterm_is_honored() {
+        // No TERM trap, so the default SIGTERM behavior (exit) satisfies the
+        // escalation's first step and we never reach SIGKILL.
+        let child = Command::new("sh").arg("-c").arg("sleep 100").spawn().unwrap();
+        let result = wait_with_timeout(child, Duration::from_millis(100));
+        assert!(matches!(result, Err(SupervisionError::TimedOut)));
+    }
+
+    #[test]
+    fn wait_with_timeout_escalates_to_kill_for_a_child_that_ignores_sigterm() {
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg("trap '' TERM; sleep 100")
+            .spawn()
+            .unwrap();
+        let result = wait_with_timeout(child, Duration::from_millis(100));
+        assert!(matches!(result, Err(SupervisionError::Killed)));
+    }
+
+    #[test]
+    fn join_reader_returns_promptly_even_if_the_thread_is_still_running() {
+        let handle = std::thread::spawn(|| std::thread::sleep(Duration::from_secs(10)));
+        let started = std::time::Instant::now();
+        join_reader(handle);
+        assert!(started.elapsed() < READER_JOIN_TIMEOUT * 2);
+    }
+}
+
+/// Kills and reaps `child` if it's still running, discarding any error.
+/// Used to clean up a leftover process before starting a new one.
+pub fn kill_and_reap(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Joins `handle`, bounded by `READER_JOIN_TIMEOUT`.
+///
+/// A reader thread blocks on a blocking read of a child's pipe, so it only
+/// unblocks once that pipe closes. Callers must reap the child (which
+/// closes its end of the pipe) *before* calling this, or the join can hang
+/// exactly like the unbounded process wait this module exists to avoid. If
+/// the thread still hasn't finished within the bound, it's left detached —
+/// it will exit on its own once the pipe it's blocked on actually closes.
+pub fn join_reader(handle: JoinHandle<()>) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(handle.join());
+    });
+    let _ = rx.recv_timeout(READER_JOIN_TIMEOUT);
+}
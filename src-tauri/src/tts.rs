@@ -0,0 +1,456 @@
+// Cross-platform text-to-speech for reading transcripts back to the user.
+//
+// This backs an accessibility/confirmation loop: after `stop_recording`
+// returns text, the frontend can have it read aloud before `insert_text`
+// commits it, and `check_voice_system` results can be read out for users
+// relying on a screen reader.
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use std::io::Read;
+use std::process::Command;
+#[cfg(target_os = "windows")]
+use std::process::{Child, Stdio};
+use std::sync::Mutex;
+#[cfg(target_os = "windows")]
+use std::time::Duration;
+
+/// A voice exposed by the active TTS backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+    pub gender: String,
+}
+
+/// Speaking parameters shared by every backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceParams {
+    pub voice_id: Option<String>,
+    pub rate: f32,
+    pub pitch: f32,
+    pub volume: f32,
+}
+
+impl Default for VoiceParams {
+    fn default() -> Self {
+        Self {
+            voice_id: None,
+            rate: 1.0,
+            pitch: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+/// A speech-synthesis backend.
+pub trait TtsBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn is_available(&self) -> bool;
+    fn list_voices(&self) -> Result<Vec<Voice>, String>;
+    fn speak(&self, text: &str, params: &VoiceParams) -> Result<(), String>;
+    fn stop(&self) -> Result<(), String>;
+}
+
+/// Linux backend, driving `speech-dispatcher` via its `spd-say` CLI.
+pub struct SpeechDispatcherBackend;
+
+impl TtsBackend for SpeechDispatcherBackend {
+    fn name(&self) -> &'static str {
+        "speech-dispatcher"
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("spd-say").is_ok()
+    }
+
+    fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        let output = Command::new("spd-say")
+            .arg("--list-synthesis-voices")
+            .output()
+            .map_err(|e| format!("Failed to list speech-dispatcher voices: {}", e))?;
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let voices = text
+            .lines()
+            .filter_map(|line| {
+                // Format: "name    language    variant"
+                let mut fields = line.split_whitespace();
+                let name = fields.next()?.to_string();
+                let language = fields.next().unwrap_or("en").to_string();
+                Some(Voice {
+                    id: name.clone(),
+                    name,
+                    language,
+                    gender: "unknown".to_string(),
+                })
+            })
+            .collect();
+
+        Ok(voices)
+    }
+
+    fn speak(&self, text: &str, params: &VoiceParams) -> Result<(), String> {
+        let mut cmd = Command::new("spd-say");
+        cmd.arg("--rate").arg(scale_to_spd_range(params.rate).to_string());
+        cmd.arg("--pitch").arg(scale_to_spd_range(params.pitch).to_string());
+        cmd.arg("--volume").arg(scale_to_spd_range(params.volume).to_string());
+        if let Some(voice_id) = &params.voice_id {
+            cmd.arg("--voice").arg(voice_id);
+        }
+        cmd.arg("--").arg(text);
+
+        let output = cmd
+            .output()
+            .map_err(|e| format!("Failed to run spd-say: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "spd-say failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        Command::new("spd-say")
+            .arg("--stop")
+            .output()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to stop speech-dispatcher: {}", e))
+    }
+}
+
+/// Maps a 0.0-2.0 rate/pitch/volume multiplier onto speech-dispatcher's
+/// -100..100 scale, where 0 is the default.
+fn scale_to_spd_range(multiplier: f32) -> i32 {
+    (((multiplier - 1.0) * 100.0).clamp(-100.0, 100.0)) as i32
+}
+
+/// Windows backend, driving SAPI/WinRT speech synthesis through PowerShell's
+/// `System.Speech` wrapper (avoids a direct WinRT binding dependency for a
+/// feature this small).
+///
+/// `speak` hands the PowerShell child to `current` for the duration of the
+/// utterance so a concurrent `stop` can reach in and kill it; `speak` itself
+/// polls rather than blocking on `wait()` so the handle is never locked away
+/// from `stop` for the whole utterance.
+#[cfg(target_os = "windows")]
+pub struct WindowsSapiBackend {
+    current: Mutex<Option<Child>>,
+}
+
+#[cfg(target_os = "windows")]
+impl WindowsSapiBackend {
+    fn new() -> Self {
+        Self {
+            current: Mutex::new(None),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl TtsBackend for WindowsSapiBackend {
+    fn name(&self) -> &'static str {
+        "sapi"
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("powershell").is_ok()
+    }
+
+    fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        let script = "Add-Type -AssemblyName System.Speech; \
+            $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+            $s.GetInstalledVoices() | ForEach-Object { \
+                $i = $_.VoiceInfo; \"$($i.Id)|$($i.Name)|$($i.Culture)|$($i.Gender)\" }";
+
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", script])
+            .output()
+            .map_err(|e| format!("Failed to list SAPI voices: {}", e))?;
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let voices = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, '|');
+                Some(Voice {
+                    id: fields.next()?.to_string(),
+                    name: fields.next()?.to_string(),
+                    language: fields.next().unwrap_or("en-US").to_string(),
+                    gender: fields.next().unwrap_or("unknown").to_string(),
+                })
+            })
+            .collect();
+
+        Ok(voices)
+    }
+
+    fn speak(&self, text: &str, params: &VoiceParams) -> Result<(), String> {
+        let escaped = text.replace('\'', "''");
+        let voice_select = params
+            .voice_id
+            .as_ref()
+            .map(|id| format!("$s.SelectVoice('{}'); ", id.replace('\'', "''")))
+            .unwrap_or_default();
+
+        let script = format!(
+            "Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+             {voice_select}$s.Rate = {rate}; $s.Volume = {volume}; $s.Speak('{text}');",
+            voice_select = voice_select,
+            rate = scale_to_sapi_rate(params.rate),
+            volume = (params.volume.clamp(0.0, 1.0) * 100.0) as i32,
+            text = escaped,
+        );
+
+        let child = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run SAPI synthesis: {}", e))?;
+
+        *self.current.lock().unwrap() = Some(child);
+
+        let status = loop {
+            let mut slot = self.current.lock().unwrap();
+            let Some(child) = slot.as_mut() else {
+                // `stop` already took and killed it.
+                return Ok(());
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {
+                    drop(slot);
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(format!("Failed to poll SAPI synthesis: {}", e)),
+            }
+        };
+
+        let mut child = self.current.lock().unwrap().take().unwrap();
+        if !status.success() {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            return Err(format!("SAPI synthesis failed: {}", stderr));
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        if let Some(mut child) = self.current.lock().unwrap().take() {
+            child
+                .kill()
+                .map_err(|e| format!("Failed to stop SAPI speech: {}", e))?;
+            let _ = child.wait();
+        }
+        Ok(())
+    }
+}
+
+/// Maps a 0.0-2.0 rate multiplier onto SAPI's -10..10 scale.
+#[cfg(target_os = "windows")]
+fn scale_to_sapi_rate(multiplier: f32) -> i32 {
+    (((multiplier - 1.0) * 10.0).clamp(-10.0, 10.0)) as i32
+}
+
+/// macOS backend, driving `AVSpeechSynthesizer` through the `say` CLI.
+#[cfg(target_os = "macos")]
+pub struct AvSpeechBackend;
+
+#[cfg(target_os = "macos")]
+impl TtsBackend for AvSpeechBackend {
+    fn name(&self) -> &'static str {
+        "avspeech"
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("say").is_ok()
+    }
+
+    fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        let output = Command::new("say")
+            .arg("-v")
+            .arg("?")
+            .output()
+            .map_err(|e| format!("Failed to list AVSpeech voices: {}", e))?;
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let voices = text
+            .lines()
+            .filter_map(|line| {
+                // Format: "name    language   # sample text"
+                let name = line.split_whitespace().next()?.to_string();
+                let language = line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or("en_US")
+                    .to_string();
+                Some(Voice {
+                    id: name.clone(),
+                    name,
+                    language,
+                    gender: "unknown".to_string(),
+                })
+            })
+            .collect();
+
+        Ok(voices)
+    }
+
+    fn speak(&self, text: &str, params: &VoiceParams) -> Result<(), String> {
+        let mut cmd = Command::new("say");
+        if let Some(voice_id) = &params.voice_id {
+            cmd.arg("-v").arg(voice_id);
+        }
+        cmd.arg("-r")
+            .arg(((params.rate.clamp(0.25, 4.0)) * 175.0).to_string());
+        cmd.arg("--").arg(text);
+
+        let output = cmd
+            .output()
+            .map_err(|e| format!("Failed to run say: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "say failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        Command::new("killall")
+            .args(["say"])
+            .output()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to stop say: {}", e))
+    }
+}
+
+/// Offline fallback used when no platform speech service is installed.
+pub struct EspeakBackend;
+
+impl TtsBackend for EspeakBackend {
+    fn name(&self) -> &'static str {
+        "espeak-ng"
+    }
+
+    fn is_available(&self) -> bool {
+        which::which("espeak-ng").is_ok()
+    }
+
+    fn list_voices(&self) -> Result<Vec<Voice>, String> {
+        let output = Command::new("espeak-ng")
+            .arg("--voices")
+            .output()
+            .map_err(|e| format!("Failed to list espeak-ng voices: {}", e))?;
+
+        let text = String::from_utf8_lossy(&output.stdout);
+        let voices = text
+            .lines()
+            .skip(1) // header row
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                fields.next()?; // Pty
+                let language = fields.next()?.to_string();
+                fields.next()?; // Age/Gender column
+                let name = fields.next()?.to_string();
+                Some(Voice {
+                    id: name.clone(),
+                    name,
+                    language,
+                    gender: "unknown".to_string(),
+                })
+            })
+            .collect();
+
+        Ok(voices)
+    }
+
+    fn speak(&self, text: &str, params: &VoiceParams) -> Result<(), String> {
+        let mut cmd = Command::new("espeak-ng");
+        if let Some(voice_id) = &params.voice_id {
+            cmd.arg("-v").arg(voice_id);
+        }
+        cmd.arg("-s").arg((params.rate.clamp(0.25, 4.0) * 175.0).to_string());
+        cmd.arg("-p").arg((params.pitch.clamp(0.0, 2.0) * 50.0).to_string());
+        cmd.arg("-a").arg((params.volume.clamp(0.0, 2.0) * 100.0).to_string());
+        cmd.arg("--").arg(text);
+
+        let output = cmd
+            .output()
+            .map_err(|e| format!("Failed to run espeak-ng: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "espeak-ng failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        // Does not interrupt speech: `speak` waits for espeak-ng to finish
+        // and keeps no handle to the running process, so an utterance
+        // already in progress plays to the end.
+        Ok(())
+    }
+}
+
+/// Picks the best backend for the current OS, falling back to espeak-ng if
+/// the platform-native service isn't installed.
+pub fn detect_tts_backend() -> Box<dyn TtsBackend> {
+    #[cfg(target_os = "windows")]
+    {
+        let backend = WindowsSapiBackend::new();
+        if backend.is_available() {
+            return Box::new(backend);
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let backend = AvSpeechBackend;
+        if backend.is_available() {
+            return Box::new(backend);
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let backend = SpeechDispatcherBackend;
+        if backend.is_available() {
+            return Box::new(backend);
+        }
+    }
+
+    Box::new(EspeakBackend)
+}
+
+/// Tauri-managed state holding the active backend and current voice params.
+pub struct TtsState {
+    pub backend: Box<dyn TtsBackend>,
+    pub params: Mutex<VoiceParams>,
+}
+
+impl Default for TtsState {
+    fn default() -> Self {
+        Self {
+            backend: detect_tts_backend(),
+            params: Mutex::new(VoiceParams::default()),
+        }
+    }
+}